@@ -1,8 +1,11 @@
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::{Arc, Weak};
+use std::time::{Duration, Instant};
 
 use fnv::FnvHashMap;
 use tokio::stream;
-use tokio::sync::{broadcast, mpsc};
+use tokio::sync::{broadcast, mpsc, oneshot};
+use tokio::time::{delay_for, timeout};
 
 use crate::client::single::{connect_internal, ConnectionContext};
 use crate::client::MessageSender;
@@ -16,6 +19,113 @@ use crate::{MessageResponse, MessageSendError};
 use futures_core::Stream;
 use tokio::sync::broadcast::RecvError;
 
+/// Monotonically increasing id used to identify a [`ConnectionHandle`] across
+/// reconnects, since the `Arc` behind it is replaced whenever the underlying
+/// socket is re-established.
+static NEXT_CONNECTION_ID: AtomicU64 = AtomicU64::new(0);
+
+/// Per-topic fan-out senders, keyed by channel name.
+type TopicRouter = Arc<std::sync::Mutex<FnvHashMap<String, broadcast::Sender<Result<Event, Error>>>>>;
+
+/// Drops topic entries with no subscribers left, so channels that were
+/// subscribed to once don't keep their fan-out sender alive forever.
+fn prune_dead_topics(topic_router: &TopicRouter) {
+    topic_router
+        .lock()
+        .unwrap()
+        .retain(|_, sender| sender.receiver_count() > 0);
+}
+
+/// A connection that was re-established after dropping out, together with the
+/// id of the connection it replaces. Sent back into the pool's message loop so
+/// it can swap the stale `Arc<ConnectionHandle>` for the fresh one.
+struct Reconnected {
+    old_id: u64,
+    handle: ConnectionHandle,
+}
+
+/// Outcome of a reconnect-with-backoff attempt, sent back into the pool's
+/// message loop so it can either adopt the new connection or drop the one
+/// that never came back.
+enum ReconnectOutcome {
+    Reconnected(Reconnected),
+    GaveUp { old_id: u64 },
+}
+
+/// Builds the `ReconnectOutcome` to report for a connection that was
+/// previously identified by `old_id`, once its reconnect-with-backoff has
+/// either produced a replacement or exhausted its attempts.
+fn reconnect_outcome(old_id: u64, new_handle: Option<ConnectionHandle>) -> ReconnectOutcome {
+    match new_handle {
+        Some(handle) => ReconnectOutcome::Reconnected(Reconnected { old_id, handle }),
+        None => ReconnectOutcome::GaveUp { old_id },
+    }
+}
+
+/// Out-of-band commands for the pool's message loop that aren't themselves
+/// IRC messages.
+enum PoolControl {
+    /// Part every joined channel, close every connection and terminate the
+    /// message loop.
+    Shutdown,
+    /// Record that `channel` needs at least `capabilities` from whichever
+    /// connection ends up joining it. `ack` is fired once the requirement
+    /// has actually been recorded, so callers can await its effect instead
+    /// of racing a subsequent `JOIN` against this message.
+    RequireCapabilities {
+        channel: String,
+        capabilities: Capabilities,
+        ack: oneshot::Sender<()>,
+    },
+}
+
+/// The IRCv3 capabilities a connection has negotiated with Twitch, packed
+/// into a bitflag set.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Capabilities(u32);
+
+const CAPABILITY_TAGS: u32 = 1 << 0;
+const CAPABILITY_COMMANDS: u32 = 1 << 1;
+const CAPABILITY_MEMBERSHIP: u32 = 1 << 2;
+
+impl Capabilities {
+    /// A capability set requesting nothing at all.
+    pub fn none() -> Self {
+        Capabilities(0)
+    }
+
+    /// Request or clear the `twitch.tv/tags` capability.
+    pub fn with_tags(mut self, enabled: bool) -> Self {
+        self.set(CAPABILITY_TAGS, enabled);
+        self
+    }
+
+    /// Request or clear the `twitch.tv/commands` capability.
+    pub fn with_commands(mut self, enabled: bool) -> Self {
+        self.set(CAPABILITY_COMMANDS, enabled);
+        self
+    }
+
+    /// Request or clear the `twitch.tv/membership` capability.
+    pub fn with_membership(mut self, enabled: bool) -> Self {
+        self.set(CAPABILITY_MEMBERSHIP, enabled);
+        self
+    }
+
+    /// Returns `true` if this set has at least every capability in `other`.
+    pub fn includes(&self, other: &Capabilities) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    fn set(&mut self, flag: u32, enabled: bool) {
+        if enabled {
+            self.0 |= flag;
+        } else {
+            self.0 &= !flag;
+        }
+    }
+}
+
 /// Create a connection pool
 pub async fn connect(
     cfg: &Arc<TwitchClientConfig>,
@@ -23,18 +133,46 @@ pub async fn connect(
 ) -> Result<ConnectionPoolHandle, Error> {
     let (message_sender, mut message_receiver) =
         mpsc::channel::<SentClientMessage>(cfg.channel_buffer);
+    let (reconnect_sender, mut reconnect_receiver) = mpsc::channel::<ReconnectOutcome>(16);
+    let (control_sender, mut control_receiver) = mpsc::channel::<PoolControl>(1);
+    let (shutdown_complete_sender, shutdown_complete_receiver) = oneshot::channel::<()>();
     let rate_limiter = Arc::new(RateLimiter::from(&cfg.rate_limiter));
     let (event_sender, event_receiver) = broadcast::channel(cfg.channel_buffer);
+    let topic_router: TopicRouter = Arc::new(std::sync::Mutex::new(FnvHashMap::default()));
+
+    {
+        // fan out the firehose into per-topic broadcast channels, see TopicRouter
+        let mut firehose = event_sender.subscribe();
+        let topic_router = topic_router.clone();
+        tokio::spawn(async move {
+            loop {
+                match firehose.recv().await {
+                    Ok(event) => {
+                        let topic = event.as_ref().ok().and_then(Event::channel);
+                        if let Some(topic) = topic {
+                            let sender = topic_router.lock().unwrap().get(topic).cloned();
+                            if let Some(sender) = sender {
+                                sender.send(event).ok();
+                            }
+                        }
+                    }
+                    Err(RecvError::Lagged(_)) => continue,
+                    Err(RecvError::Closed) => break,
+                }
+            }
+        });
+    }
 
     let mut default_connections = vec![];
     for _ in 0..pool_cfg.init_connections {
-        let conn = new_connection(&cfg, &rate_limiter, &event_sender).await?;
+        let conn = new_connection(&cfg, &rate_limiter, &event_sender, Capabilities::none()).await?;
         default_connections.push(Arc::new(conn));
     }
 
     let pool = ConnectionPool {
         whisper_connection: default_connections[0].clone(),
         channel_connections_map: Default::default(),
+        channel_capabilities: Default::default(),
         event_sender: event_sender.clone(),
         event_receiver,
         connections: default_connections,
@@ -44,20 +182,73 @@ pub async fn connect(
         // capture variables for spawned task
         let cfg = cfg.clone();
         let event_sender = event_sender.clone();
+        let pool_cfg = pool_cfg.clone();
+        let topic_router = topic_router.clone();
         tokio::spawn(async move {
             use futures_util::stream::StreamExt;
 
+            let reconnect_ctx = ReconnectContext {
+                cfg: cfg.clone(),
+                rate_limiter: rate_limiter.clone(),
+                event_sender: event_sender.clone(),
+                pool_cfg: pool_cfg.clone(),
+                reconnect_sender,
+            };
+
             let mut pool = pool;
-            while let Some(SentClientMessage {
-                message: client_message,
-                responder,
-            }) = message_receiver.recv().await
-            {
+            let mut idle_tick = tokio::time::interval(pool_cfg.idle_timeout);
+            let mut idle_since: FnvHashMap<u64, Instant> = FnvHashMap::default();
+            'pool_loop: loop {
+                let sent_message = tokio::select! {
+                    received = message_receiver.recv() => match received {
+                        Some(sent_message) => sent_message,
+                        None => break 'pool_loop,
+                    },
+                    reconnected = reconnect_receiver.recv() => {
+                        match reconnected {
+                            Some(ReconnectOutcome::Reconnected(reconnected)) => {
+                                pool.replace_connection(reconnected)
+                            }
+                            Some(ReconnectOutcome::GaveUp { old_id }) => {
+                                pool.remove_connection(old_id, &reconnect_ctx)
+                            }
+                            None => break 'pool_loop,
+                        }
+                        continue;
+                    }
+                    _ = idle_tick.tick() => {
+                        pool.reap_idle_connections(&pool_cfg, &mut idle_since).await;
+                        prune_dead_topics(&topic_router);
+                        continue;
+                    }
+                    control = control_receiver.recv() => {
+                        match control {
+                            Some(PoolControl::Shutdown) => {
+                                pool.shutdown(pool_cfg.part_timeout).await;
+                                break 'pool_loop;
+                            }
+                            Some(PoolControl::RequireCapabilities {
+                                channel,
+                                capabilities,
+                                ack,
+                            }) => {
+                                pool.channel_capabilities.insert(channel, capabilities);
+                                ack.send(()).ok();
+                                continue;
+                            }
+                            None => break 'pool_loop,
+                        }
+                    }
+                };
+
+                let SentClientMessage {
+                    message: client_message,
+                    responder,
+                } = sent_message;
                 match &client_message {
                     ClientMessage::PrivMsg { channel, .. } => {
                         if let Some(handle) = pool.get_channel_connection(channel) {
-                            handle
-                                .send(client_message)
+                            pool.send_tracked(handle, client_message, &reconnect_ctx)
                                 .await
                                 .respond_with_errors(responder);
                         } else {
@@ -67,21 +258,20 @@ pub async fn connect(
                         }
                     }
                     ClientMessage::Whisper { .. } => {
-                        pool.whisper_connection
-                            .send(client_message)
+                        let handle = pool.whisper_connection.clone();
+                        pool.send_tracked(handle, client_message, &reconnect_ctx)
                             .await
                             .respond_with_errors(responder);
                     }
                     ClientMessage::Ping | ClientMessage::Pong => {
-                        pool.whisper_connection
-                            .send(client_message)
+                        let handle = pool.whisper_connection.clone();
+                        pool.send_tracked(handle, client_message, &reconnect_ctx)
                             .await
                             .respond_with_errors(responder);
                     }
                     ClientMessage::Part(channel) => {
                         if let Some(handle) = pool.get_channel_connection(channel) {
-                            handle
-                                .send(client_message)
+                            pool.send_tracked(handle, client_message, &reconnect_ctx)
                                 .await
                                 .respond_with_errors(responder);
                         } else {
@@ -98,14 +288,23 @@ pub async fn connect(
                                 .await
                                 .respond_with_errors(responder);
                         } else {
-                            // get connection with the lowest amount of joined channels
+                            let required_capabilities = pool
+                                .channel_capabilities
+                                .get(channel)
+                                .copied()
+                                .unwrap_or_else(Capabilities::none);
+
+                            // get the least-loaded connection that already has the required
+                            // capabilities negotiated
                             let handle = stream::iter(&pool.connections)
                                 .filter_map(|handle| {
                                     let threshold = pool_cfg.threshold;
                                     async move {
                                         let count =
                                             handle.context.joined_channels.read().await.len();
-                                        if count <= threshold as usize {
+                                        if count <= threshold as usize
+                                            && handle.capabilities.includes(&required_capabilities)
+                                        {
                                             Some((handle, count))
                                         } else {
                                             None
@@ -124,14 +323,35 @@ pub async fn connect(
                                     .send(client_message)
                                     .await
                                     .respond_with_errors(responder);
+                            } else if pool.connections.len() >= pool_cfg.connection_limit as usize {
+                                debug!("Connection limit reached, rejecting new channel join.");
+                                responder
+                                    .send(Err(MessageSendError::ConnectionLimitReached(
+                                        client_message,
+                                    )))
+                                    .ok();
                             } else {
-                                debug!("Adding new connection to the pool.");
-                                let conn_result =
-                                    new_connection(&cfg, &rate_limiter, &event_sender)
-                                        .await
-                                        .map_err(|e| {
-                                            MessageSendError::NewConnectionFailed(format!("{}", e))
-                                        });
+                                debug!("Adding new connection to the pool with required capabilities.");
+                                let conn_result = timeout(
+                                    pool_cfg.connect_timeout,
+                                    new_connection(
+                                        &cfg,
+                                        &rate_limiter,
+                                        &event_sender,
+                                        required_capabilities,
+                                    ),
+                                )
+                                .await
+                                .map_err(|_| {
+                                    MessageSendError::NewConnectionFailed(
+                                        "timed out connecting to Twitch IRC".into(),
+                                    )
+                                })
+                                .and_then(|result| {
+                                    result.map_err(|e| {
+                                        MessageSendError::NewConnectionFailed(format!("{}", e))
+                                    })
+                                });
                                 match conn_result {
                                     Ok(conn) => {
                                         let channel = channel.clone();
@@ -176,15 +396,23 @@ pub async fn connect(
                                 break;
                             }
                         }
+                        break 'pool_loop;
                     }
                 }
             }
+
+            drop(pool);
+            shutdown_complete_sender.send(()).ok();
         });
     }
 
     let pool_handle = ConnectionPoolHandle {
         event_sender,
         message_sender: MessageSender::from(message_sender),
+        control_sender,
+        shutdown_complete: Arc::new(std::sync::Mutex::new(Some(shutdown_complete_receiver))),
+        topic_router,
+        topic_channel_buffer: cfg.channel_buffer,
     };
 
     Ok(pool_handle)
@@ -194,15 +422,108 @@ async fn new_connection(
     cfg: &Arc<TwitchClientConfig>,
     rate_limiter: &Arc<RateLimiter>,
     event_sender: &broadcast::Sender<Result<Event, Error>>,
+    capabilities: Capabilities,
 ) -> Result<ConnectionHandle, Error> {
     let (sender, context) = connect_internal(
         cfg,
         rate_limiter.clone(),
         InternalSender(event_sender.clone()),
+        capabilities,
     )
     .await?;
 
-    Ok(ConnectionHandle { sender, context })
+    Ok(ConnectionHandle {
+        id: NEXT_CONNECTION_ID.fetch_add(1, Ordering::Relaxed),
+        sender,
+        context,
+        capabilities,
+        reconnecting: AtomicBool::new(false),
+    })
+}
+
+/// Repeatedly attempts to establish a fresh connection with a capped
+/// exponential backoff between tries, then replays the `JOIN`s for every
+/// channel the dropped connection used to own.
+async fn reconnect_with_backoff(
+    cfg: &Arc<TwitchClientConfig>,
+    rate_limiter: &Arc<RateLimiter>,
+    event_sender: &broadcast::Sender<Result<Event, Error>>,
+    pool_cfg: &PoolConfig,
+    capabilities: Capabilities,
+    channels: &[String],
+) -> Option<ConnectionHandle> {
+    let mut delay = pool_cfg.reconnect_initial_delay;
+    for attempt in 1..=pool_cfg.max_reconnect_attempts {
+        match new_connection(cfg, rate_limiter, event_sender, capabilities).await {
+            Ok(handle) => {
+                for channel in channels {
+                    if let Err(e) = handle.send(ClientMessage::Join(channel.clone())).await {
+                        warn!("Failed to rejoin channel {} after reconnect: {}", channel, e);
+                    }
+                }
+                return Some(handle);
+            }
+            Err(e) => {
+                warn!(
+                    "Reconnect attempt {}/{} failed: {}",
+                    attempt, pool_cfg.max_reconnect_attempts, e
+                );
+                delay_for(delay).await;
+                delay = std::cmp::min(delay * 2, pool_cfg.reconnect_max_delay);
+            }
+        }
+    }
+    error!(
+        "Giving up reconnecting after {} attempts.",
+        pool_cfg.max_reconnect_attempts
+    );
+    None
+}
+
+/// Keeps retrying `reconnect_with_backoff` for the dedicated whisper
+/// connection, unlike a regular connection's single backoff cycle, since
+/// there's no later `JOIN` to trigger a fresh attempt if this one also
+/// gives up. Reports success back through `ctx.reconnect_sender` the same
+/// way a regular reconnect does, so it's picked up by `replace_connection`.
+fn spawn_whisper_replacement(old_id: u64, ctx: &ReconnectContext) {
+    let cfg = ctx.cfg.clone();
+    let rate_limiter = ctx.rate_limiter.clone();
+    let event_sender = ctx.event_sender.clone();
+    let pool_cfg = ctx.pool_cfg.clone();
+    let mut reconnect_sender = ctx.reconnect_sender.clone();
+    tokio::spawn(async move {
+        loop {
+            if let Some(handle) = reconnect_with_backoff(
+                &cfg,
+                &rate_limiter,
+                &event_sender,
+                &pool_cfg,
+                Capabilities::none(),
+                &[],
+            )
+            .await
+            {
+                reconnect_sender
+                    .send(ReconnectOutcome::Reconnected(Reconnected { old_id, handle }))
+                    .await
+                    .ok();
+                break;
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod reconnect_outcome_tests {
+    use super::*;
+
+    #[test]
+    fn giving_up_reports_the_dead_connection_id() {
+        match reconnect_outcome(42, None) {
+            ReconnectOutcome::GaveUp { old_id } => assert_eq!(old_id, 42),
+            ReconnectOutcome::Reconnected(_) => panic!("expected GaveUp"),
+        }
+    }
 }
 
 /// Connection pool settings
@@ -215,11 +536,48 @@ pub struct PoolConfig {
     /// When all connections reach this number of joined channels, a new connection
     /// will be created
     pub threshold: u32,
+    /// Maximum number of reconnect attempts before a dropped connection's channels
+    /// are given up on
+    pub max_reconnect_attempts: u32,
+    /// Delay after the first failed reconnect attempt, before the next one.
+    /// The first attempt itself is made immediately.
+    pub reconnect_initial_delay: Duration,
+    /// Upper bound the reconnect delay is capped at, doubling from
+    /// `reconnect_initial_delay` on each successive failure
+    pub reconnect_max_delay: Duration,
+    /// How long a connection may hold zero joined channels before it is
+    /// closed and removed from the pool
+    pub idle_timeout: Duration,
+    /// The pool never reaps idle connections below this count, regardless of
+    /// `idle_timeout`
+    pub min_connections: u32,
+    /// Timeout for establishing a new connection when joining a channel
+    pub connect_timeout: Duration,
+    /// How long `shutdown` waits for a server PART acknowledgement for a
+    /// single channel before giving up on it and moving on
+    pub part_timeout: Duration,
+}
+
+/// Bundles the state needed to supervise and recover a dropped connection,
+/// so it doesn't have to be threaded through every message branch by hand.
+struct ReconnectContext {
+    cfg: Arc<TwitchClientConfig>,
+    rate_limiter: Arc<RateLimiter>,
+    event_sender: broadcast::Sender<Result<Event, Error>>,
+    pool_cfg: PoolConfig,
+    reconnect_sender: mpsc::Sender<ReconnectOutcome>,
 }
 
 struct ConnectionHandle {
+    id: u64,
     sender: MessageSender,
     context: Arc<ConnectionContext>,
+    /// The IRCv3 capabilities negotiated for this connection via the
+    /// automatic CAP REQ in `new_connection`.
+    capabilities: Capabilities,
+    /// Set while a reconnect attempt for this connection is already in flight,
+    /// so concurrent failed sends don't spawn duplicate supervisors.
+    reconnecting: AtomicBool,
 }
 
 impl ConnectionHandle {
@@ -242,6 +600,10 @@ impl Drop for ConnectionHandle {
 pub struct ConnectionPoolHandle {
     event_sender: broadcast::Sender<Result<Event, Error>>,
     message_sender: MessageSender,
+    control_sender: mpsc::Sender<PoolControl>,
+    shutdown_complete: Arc<std::sync::Mutex<Option<oneshot::Receiver<()>>>>,
+    topic_router: TopicRouter,
+    topic_channel_buffer: usize,
 }
 
 impl ConnectionPoolHandle {
@@ -267,6 +629,98 @@ impl ConnectionPoolHandle {
     pub fn sender(&self) -> &MessageSender {
         &self.message_sender
     }
+
+    /// Subscribe to only the events targeting `channel`, routed through the
+    /// per-topic fan-out (see `TopicRouter`) rather than filtered from the
+    /// shared firehose.
+    pub fn subscribe_channel(&self, channel: &str) -> impl Stream<Item = Result<Event, Error>> {
+        use tokio::stream::StreamExt;
+
+        // Subscribed while still holding the lock: otherwise a prune_dead_topics
+        // tick could see the freshly inserted, still-subscriber-less sender and
+        // evict it before `.subscribe()` below ever runs.
+        let receiver = {
+            let mut topics = self.topic_router.lock().unwrap();
+            topics
+                .entry(channel.to_string())
+                .or_insert_with(|| broadcast::channel(self.topic_channel_buffer).0)
+                .subscribe()
+        };
+
+        receiver.map(|result| match result {
+            Ok(event) => event,
+            Err(recv_err) => Err(match recv_err {
+                RecvError::Closed => EventChannelError::Closed,
+                RecvError::Lagged(_) => EventChannelError::Overflow,
+            }
+            .into()),
+        })
+    }
+
+    /// Subscribe to events matching an arbitrary `predicate`, filtered from
+    /// the shared firehose.
+    pub fn subscribe_filtered<F>(&self, predicate: F) -> impl Stream<Item = Result<Event, Error>>
+    where
+        F: Fn(&Event) -> bool + Send + Sync + 'static,
+    {
+        use tokio::stream::StreamExt;
+
+        self.subscribe_events()
+            .filter(move |result| result.as_ref().map_or(true, |event| predicate(event)))
+    }
+
+    /// The channels that currently have at least one `subscribe_channel`
+    /// topic registered, so the pool could later use this to decide which
+    /// channels actually need to stay joined.
+    pub fn active_topics(&self) -> Vec<String> {
+        self.topic_router.lock().unwrap().keys().cloned().collect()
+    }
+
+    /// Requires that `channel` only be joined on a connection that has
+    /// negotiated at least `capabilities`. If no connection currently
+    /// qualifies, the next `JOIN` for this channel spins up a fresh one
+    /// negotiating them, or fails with `ConnectionLimitReached` if the pool
+    /// already holds `connection_limit` connections.
+    ///
+    /// Waits for the pool to actually record the requirement before
+    /// returning, so a `JOIN` sent right after this call is guaranteed to
+    /// see it.
+    pub async fn require_capabilities(&self, channel: impl Into<String>, capabilities: Capabilities) {
+        let (ack, ack_receiver) = oneshot::channel();
+        let mut control_sender = self.control_sender.clone();
+        if control_sender
+            .send(PoolControl::RequireCapabilities {
+                channel: channel.into(),
+                capabilities,
+                ack,
+            })
+            .await
+            .is_err()
+        {
+            // message loop is already gone
+            return;
+        }
+        ack_receiver.await.ok();
+    }
+
+    /// Gracefully tears down the pool: parts every joined channel and waits
+    /// for the server's acknowledgement (or a per-channel timeout), then
+    /// closes every connection and awaits the message loop's termination.
+    ///
+    /// This lets a single external signal (e.g. the caller's own ctrl-c
+    /// handler) drive a clean shutdown instead of relying on `Drop`.
+    pub async fn shutdown(self) {
+        let mut control_sender = self.control_sender.clone();
+        if control_sender.send(PoolControl::Shutdown).await.is_err() {
+            // message loop is already gone
+            return;
+        }
+
+        let done = self.shutdown_complete.lock().unwrap().take();
+        if let Some(done) = done {
+            done.await.ok();
+        }
+    }
 }
 
 struct ConnectionPool {
@@ -280,6 +734,9 @@ struct ConnectionPool {
     whisper_connection: Arc<ConnectionHandle>,
     /// weak connection handles for individual channels
     channel_connections_map: FnvHashMap<String, Weak<ConnectionHandle>>,
+    /// capabilities a channel requires from whichever connection joins it,
+    /// set via `ConnectionPoolHandle::require_capabilities`
+    channel_capabilities: FnvHashMap<String, Capabilities>,
 }
 
 impl ConnectionPool {
@@ -288,4 +745,283 @@ impl ConnectionPool {
             .get(channel)
             .and_then(|weak| weak.upgrade())
     }
+
+    /// Sends a message on `handle`, and if the send fails, kicks off a
+    /// reconnect-with-backoff for that connection unless one is already
+    /// running.
+    async fn send_tracked(
+        &self,
+        handle: Arc<ConnectionHandle>,
+        msg: ClientMessage,
+        ctx: &ReconnectContext,
+    ) -> Result<MessageResponse, MessageSendError> {
+        let result = handle.send(msg).await;
+        if result.is_err()
+            && handle
+                .reconnecting
+                .compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst)
+                .is_ok()
+        {
+            let id = handle.id;
+            let capabilities = handle.capabilities;
+            let cfg = ctx.cfg.clone();
+            let rate_limiter = ctx.rate_limiter.clone();
+            let event_sender = ctx.event_sender.clone();
+            let pool_cfg = ctx.pool_cfg.clone();
+            let mut reconnect_sender = ctx.reconnect_sender.clone();
+            tokio::spawn(async move {
+                let channels: Vec<String> = handle
+                    .context
+                    .joined_channels
+                    .read()
+                    .await
+                    .iter()
+                    .cloned()
+                    .collect();
+                let new_handle = reconnect_with_backoff(
+                    &cfg,
+                    &rate_limiter,
+                    &event_sender,
+                    &pool_cfg,
+                    capabilities,
+                    &channels,
+                )
+                .await;
+                reconnect_sender
+                    .send(reconnect_outcome(id, new_handle))
+                    .await
+                    .ok();
+            });
+        }
+        result
+    }
+
+    /// Swaps the stale `Arc<ConnectionHandle>` for a connection that was
+    /// re-established after dropping out, updating every place the pool
+    /// references it so in-flight senders transparently resume.
+    fn replace_connection(&mut self, reconnected: Reconnected) {
+        let Reconnected { old_id, handle } = reconnected;
+        let arc = Arc::new(handle);
+
+        // Repoint the channel mappings first, while the pool still holds the
+        // old `Arc` and its `Weak`s can still be upgraded to read the id.
+        for weak in self.channel_connections_map.values_mut() {
+            if weak.upgrade().map_or(false, |h| h.id == old_id) {
+                *weak = Arc::downgrade(&arc);
+            }
+        }
+
+        if self.whisper_connection.id == old_id {
+            self.whisper_connection = arc.clone();
+        }
+
+        if let Some(slot) = self.connections.iter_mut().find(|c| c.id == old_id) {
+            *slot = arc.clone();
+        } else {
+            self.connections.push(arc.clone());
+        }
+    }
+
+    /// Drops a connection that never came back from a reconnect attempt,
+    /// along with every channel mapping still pointing at it, so those
+    /// channels are free to be rejoined on a new connection instead of
+    /// staying wedged to a dead one forever.
+    ///
+    /// If the dead connection was the dedicated `whisper_connection`, it
+    /// can't simply be dropped - there's always exactly one of it - so a
+    /// replacement is spawned in the background and swapped in via the
+    /// usual `replace_connection` path once it comes up.
+    fn remove_connection(&mut self, old_id: u64, ctx: &ReconnectContext) {
+        self.connections.retain(|c| c.id != old_id);
+        self.channel_connections_map
+            .retain(|_, weak| weak.upgrade().map_or(false, |h| h.id != old_id));
+
+        if self.whisper_connection.id == old_id {
+            error!("Whisper connection exhausted its reconnect attempts; retrying in the background.");
+            spawn_whisper_replacement(old_id, ctx);
+        }
+    }
+
+    /// Closes and removes connections that have held zero joined channels
+    /// for longer than `idle_timeout`, never reaping below `min_connections`
+    /// and never touching the dedicated `whisper_connection`.
+    async fn reap_idle_connections(
+        &mut self,
+        pool_cfg: &PoolConfig,
+        idle_since: &mut FnvHashMap<u64, Instant>,
+    ) {
+        let mut reap_ids = vec![];
+        for conn in &self.connections {
+            if Arc::ptr_eq(conn, &self.whisper_connection) {
+                continue;
+            }
+
+            let joined = conn.context.joined_channels.read().await.len();
+            if joined == 0 {
+                let idle_since = *idle_since.entry(conn.id).or_insert_with(Instant::now);
+                if idle_since.elapsed() >= pool_cfg.idle_timeout {
+                    reap_ids.push(conn.id);
+                }
+            } else {
+                idle_since.remove(&conn.id);
+            }
+        }
+
+        let to_reap = select_reap_candidates(
+            &reap_ids,
+            self.connections.len(),
+            pool_cfg.min_connections as usize,
+        );
+        for id in to_reap {
+            if let Some(pos) = self.connections.iter().position(|c| c.id == id) {
+                debug!("Reaping connection idle for longer than idle_timeout.");
+                self.connections.remove(pos);
+                idle_since.remove(&id);
+            }
+        }
+    }
+
+    /// Parts every joined channel, waiting for the server's acknowledgement
+    /// (or `part_timeout`) on each, then closes every connection.
+    async fn shutdown(&mut self, part_timeout: Duration) {
+        let channels: Vec<String> = self.channel_connections_map.keys().cloned().collect();
+        // Subscribed fresh rather than reusing `self.event_receiver`: that
+        // receiver sits undrained for the pool's whole lifetime, so by
+        // shutdown time it's already lagged arbitrarily far behind.
+        let mut ack_receiver = self.event_sender.subscribe();
+        for channel in channels {
+            if let Some(handle) = self.get_channel_connection(&channel) {
+                if handle.send(ClientMessage::Part(channel.clone())).await.is_ok() {
+                    Self::await_part_ack(&mut ack_receiver, &channel, part_timeout).await;
+                }
+            }
+        }
+
+        for connection in self.connections.drain(..) {
+            connection.send(ClientMessage::Close).await.ok();
+        }
+        self.whisper_connection.send(ClientMessage::Close).await.ok();
+    }
+
+    /// Waits on `receiver` for a `PART` confirmation for `channel`, giving up
+    /// after `part_timeout`.
+    async fn await_part_ack(
+        receiver: &mut broadcast::Receiver<Result<Event<String>, Error>>,
+        channel: &str,
+        part_timeout: Duration,
+    ) {
+        let wait_for_ack = async {
+            loop {
+                match classify_part_event(receiver.recv().await, channel) {
+                    PartAckStep::Matched | PartAckStep::Stop => break,
+                    PartAckStep::Continue => continue,
+                }
+            }
+        };
+
+        if timeout(part_timeout, wait_for_ack).await.is_err() {
+            warn!("Timed out waiting for PART acknowledgement on {}", channel);
+        }
+    }
+}
+
+/// Picks which of the idle-too-long `candidate_ids` to actually reap,
+/// stopping as soon as reaping another would bring the pool below
+/// `min_connections`. `candidate_ids` is assumed already free of the
+/// dedicated whisper connection.
+fn select_reap_candidates(candidate_ids: &[u64], current_len: usize, min_connections: usize) -> Vec<u64> {
+    let mut keep_len = current_len;
+    let mut to_reap = vec![];
+    for &id in candidate_ids {
+        if keep_len <= min_connections {
+            break;
+        }
+        to_reap.push(id);
+        keep_len -= 1;
+    }
+    to_reap
+}
+
+#[cfg(test)]
+mod reap_candidate_tests {
+    use super::*;
+
+    #[test]
+    fn reaps_all_candidates_when_above_the_floor() {
+        let reaped = select_reap_candidates(&[1, 2, 3], 5, 1);
+        assert_eq!(reaped, vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn stops_at_min_connections() {
+        // 4 connections total, floor of 2: only 2 may be reaped.
+        let reaped = select_reap_candidates(&[1, 2, 3, 4], 4, 2);
+        assert_eq!(reaped, vec![1, 2]);
+    }
+
+    #[test]
+    fn reaps_nothing_already_at_the_floor() {
+        let reaped = select_reap_candidates(&[1, 2], 2, 2);
+        assert!(reaped.is_empty());
+    }
+}
+
+/// How a single event off the pool's broadcast channel bears on an
+/// in-progress wait for a `PART` acknowledgement. A lagged receiver just
+/// means missed events, not a reason to give up, so only `Closed` stops the
+/// wait outright.
+#[derive(Debug, PartialEq, Eq)]
+enum PartAckStep {
+    Matched,
+    Continue,
+    Stop,
+}
+
+fn classify_part_event(
+    received: Result<Result<Event<String>, Error>, RecvError>,
+    channel: &str,
+) -> PartAckStep {
+    match received {
+        Ok(Ok(Event::Part(part_channel))) if part_channel == channel => PartAckStep::Matched,
+        Ok(_) => PartAckStep::Continue,
+        Err(RecvError::Lagged(_)) => PartAckStep::Continue,
+        Err(RecvError::Closed) => PartAckStep::Stop,
+    }
+}
+
+#[cfg(test)]
+mod part_ack_tests {
+    use super::*;
+
+    #[test]
+    fn lagged_receiver_keeps_waiting() {
+        assert_eq!(
+            classify_part_event(Err(RecvError::Lagged(5)), "foo"),
+            PartAckStep::Continue
+        );
+    }
+
+    #[test]
+    fn closed_receiver_stops_waiting() {
+        assert_eq!(
+            classify_part_event(Err(RecvError::Closed), "foo"),
+            PartAckStep::Stop
+        );
+    }
+
+    #[test]
+    fn matching_part_stops_waiting() {
+        assert_eq!(
+            classify_part_event(Ok(Ok(Event::Part("foo".into()))), "foo"),
+            PartAckStep::Matched
+        );
+    }
+
+    #[test]
+    fn unrelated_event_keeps_waiting() {
+        assert_eq!(
+            classify_part_event(Ok(Ok(Event::Part("bar".into()))), "foo"),
+            PartAckStep::Continue
+        );
+    }
 }